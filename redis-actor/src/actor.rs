@@ -1,13 +1,64 @@
 use crate::error::Error;
+use crate::pool::{ReconnectBackoff, RedisConnectionManager, TlsConfig};
 use actix::prelude::*;
-use log::{debug, info, error};
-use redis::{aio::MultiplexedConnection, Client};
+use futures_util::StreamExt;
+use log::{debug, error, info};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use uuid::Uuid;
+
+/// Connection pool backing a [`RedisActor`].
+type Pool = bb8::Pool<RedisConnectionManager>;
+
+/// Translate a pool checkout failure into an `Error`, keeping `TimedOut` (no
+/// connection became available in time, worth retrying) distinct from `User`
+/// (the manager's `connect`/`is_valid` itself failed, e.g. auth or DNS) so
+/// callers can tell a transient wait from a fatal connection problem.
+fn pool_error(command: &'static str, key: Option<String>, err: bb8::RunError<redis::RedisError>) -> Error {
+    match err {
+        bb8::RunError::TimedOut => Error::PoolTimeout { command, key },
+        bb8::RunError::User(err) => Error::Io(err),
+    }
+}
+
+/// Control message sent to a running subscription task.
+enum SubscriptionControl {
+    Unsubscribe,
+}
 
 /// Actix Redis cache backend actor.
 pub struct RedisActor {
-    #[allow(dead_code)]
     connection_info: String,
-    connection: Option<MultiplexedConnection>,
+    pool_config: PoolConfig,
+    pool: Option<Pool>,
+    /// Per-channel handle used by [`Unsubscribe`] to stop the task reading that
+    /// channel's pub/sub stream.
+    subscriptions: HashMap<String, UnboundedSender<SubscriptionControl>>,
+}
+
+#[derive(Debug, Clone)]
+struct PoolConfig {
+    max_size: u32,
+    min_idle: Option<u32>,
+    connection_timeout: Duration,
+    idle_timeout: Option<Duration>,
+    reconnect_backoff: ReconnectBackoff,
+    tls: TlsConfig,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_size: 10,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(5),
+            idle_timeout: None,
+            reconnect_backoff: ReconnectBackoff::default(),
+            tls: TlsConfig::default(),
+        }
+    }
 }
 
 impl RedisActor {
@@ -18,34 +69,113 @@ impl RedisActor {
     pub fn builder() -> RedisActorBuilder {
         RedisActorBuilder::default()
     }
-    
+
     pub fn start(connection_info: String) -> Addr<RedisActor> {
-        Supervisor::start(|_| {
-            RedisActor {
-                connection_info,
-                connection: None,
-            }
+        Supervisor::start(|_| RedisActor {
+            connection_info,
+            pool_config: PoolConfig::default(),
+            pool: None,
+            subscriptions: HashMap::new(),
         })
     }
+
+    async fn build_pool(connection_info: &str, config: &PoolConfig) -> Result<Pool, Error> {
+        let manager = RedisConnectionManager::new(
+            connection_info,
+            config.reconnect_backoff.clone(),
+            config.tls.clone(),
+        )?;
+        bb8::Pool::builder()
+            .max_size(config.max_size)
+            .min_idle(config.min_idle)
+            .connection_timeout(config.connection_timeout)
+            .idle_timeout(config.idle_timeout)
+            .build(manager)
+            .await
+            .map_err(Error::from)
+    }
 }
 
 pub struct RedisActorBuilder {
     connection_info: String,
+    pool_config: PoolConfig,
 }
 
 impl Default for RedisActorBuilder {
     fn default() -> Self {
         RedisActorBuilder {
             connection_info: "redis://127.0.0.1/".to_owned(),
+            pool_config: PoolConfig::default(),
         }
     }
 }
 
 impl RedisActorBuilder {
+    pub fn connection_info(mut self, connection_info: impl Into<String>) -> Self {
+        self.connection_info = connection_info.into();
+        self
+    }
+
+    /// Maximum number of connections the pool will open.
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.pool_config.max_size = max_size;
+        self
+    }
+
+    /// Minimum number of idle connections the pool tries to maintain.
+    pub fn min_idle(mut self, min_idle: u32) -> Self {
+        self.pool_config.min_idle = Some(min_idle);
+        self
+    }
+
+    /// How long a checkout waits for a connection before failing with `Error::PoolTimeout`.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_config.connection_timeout = timeout;
+        self
+    }
+
+    /// How long an idle connection may sit in the pool before being recycled.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_config.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Exponential-backoff schedule used by each pooled connection when it
+    /// reconnects after a transient disconnect.
+    pub fn reconnect_backoff(mut self, initial_delay: Duration, max_delay: Duration, factor: u64) -> Self {
+        self.pool_config.reconnect_backoff = ReconnectBackoff {
+            initial_delay,
+            max_delay,
+            factor,
+        };
+        self
+    }
+
+    /// Custom CA certificate to trust when `connection_info` uses `rediss://`.
+    /// Only takes effect when built with the `tls-rustls` or `tls-native-tls`
+    /// feature.
+    pub fn tls_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pool_config.tls.ca_cert_path = Some(path.into());
+        self
+    }
+
+    /// Client certificate/key pair for mutual TLS against a `rediss://`
+    /// endpoint. Only takes effect when built with the `tls-rustls` or
+    /// `tls-native-tls` feature.
+    pub fn tls_client_cert(mut self, cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        self.pool_config.tls.client_cert_path = Some(cert_path.into());
+        self.pool_config.tls.client_key_path = Some(key_path.into());
+        self
+    }
+
     pub async fn build(&self) -> Result<RedisActor, Error> {
-        // let client = Client::open(self.connection_info.as_str())?;
-        // let connection = client.get_multiplexed_tokio_connection().await?;
-        Ok(RedisActor { connection_info: self.connection_info.clone(), connection: None })
+        let pool = RedisActor::build_pool(&self.connection_info, &self.pool_config).await?;
+        Ok(RedisActor {
+            connection_info: self.connection_info.clone(),
+            pool_config: self.pool_config.clone(),
+            pool: Some(pool),
+            subscriptions: HashMap::new(),
+        })
     }
 }
 
@@ -61,21 +191,17 @@ impl Actor for RedisActor {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         info!("Redis actor started");
-        let addr = self.connection_info.clone();
-        async move {
-            let client = Client::open(addr.as_ref()).unwrap();
-            client.get_multiplexed_async_connection().await
-        }
+        let connection_info = self.connection_info.clone();
+        let pool_config = self.pool_config.clone();
+        async move { RedisActor::build_pool(&connection_info, &pool_config).await }
             .into_actor(self)
-            .map(|res, act, ctx| match res {
-                Ok((con, fut)) => {
-                    debug!("Connected to redis server");
-                    dbg!("Connected to redis");
-                    act.connection = Some(con);
-                    fut.into_actor(act).wait(ctx);
-                },
+            .map(|res, act, _ctx| match res {
+                Ok(pool) => {
+                    debug!("Connected redis pool to {}", act.connection_info);
+                    act.pool = Some(pool);
+                }
                 Err(err) => {
-                    error!("Connection to redis server failed: {}", err);
+                    error!("Failed to build redis connection pool: {}", err);
                 }
             })
             .wait(ctx);
@@ -83,7 +209,7 @@ impl Actor for RedisActor {
 }
 
 /// Actix message implements request Redis value by key.
-#[derive(Message, Debug)]
+#[derive(Message, Debug, Clone)]
 #[rtype(result = "Result<Option<String>, Error>")]
 pub struct Get {
     pub key: String,
@@ -94,22 +220,50 @@ impl Handler<Get> for RedisActor {
     type Result = ResponseFuture<Result<Option<String>, Error>>;
 
     fn handle(&mut self, msg: Get, _: &mut Self::Context) -> Self::Result {
-        match self.connection {
-            Some(ref connection) => {
-                let mut con = connection.clone();
-                let fut = async move {
-                    redis::cmd("GET")
-                        .arg(msg.key)
-                        .query_async(&mut con)
-                        .await
-                        .map_err(Error::from)
-                };
-                Box::pin(fut)
-            },
-            None => {
-                Box::pin(async {Err(Error::Connection)})
-            }
-        }
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "GET",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("GET", Some(msg.key.clone()), err))?;
+            redis::cmd("GET")
+                .arg(msg.key)
+                .query_async(&mut *con)
+                .await
+                .map_err(Error::from)
+        })
+    }
+}
+
+/// Actix message fetching several keys in a single `MGET` round trip.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<Vec<Option<String>>, Error>")]
+pub struct MGet {
+    pub keys: Vec<String>,
+}
+
+/// Implementation of Actix Handler for MGet message.
+impl Handler<MGet> for RedisActor {
+    type Result = ResponseFuture<Result<Vec<Option<String>>, Error>>;
+
+    fn handle(&mut self, msg: MGet, _: &mut Self::Context) -> Self::Result {
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or(Error::NotConnected {
+                command: "MGET",
+                key: None,
+            })?;
+            let mut con = pool.get().await.map_err(|err| pool_error("MGET", None, err))?;
+            redis::cmd("MGET")
+                .arg(msg.keys)
+                .query_async(&mut *con)
+                .await
+                .map_err(Error::from)
+        })
     }
 }
 
@@ -127,29 +281,59 @@ impl Handler<Set> for RedisActor {
     type Result = ResponseFuture<Result<String, Error>>;
 
     fn handle(&mut self, msg: Set, _: &mut Self::Context) -> Self::Result {
-        match self.connection {
-            Some(ref connection) => {
-                dbg!("++++++++");
-                let mut con = connection.clone();
-                Box::pin(async move {
-                    let mut request = redis::cmd("SET");
-                    request
-                        .arg(msg.key)
-                        .arg(msg.value);
-                    if let Some(ttl) = msg.ttl {
-                        request.arg("EX").arg(ttl);
-                    };
-                    request
-                        .query_async(&mut con)
-                        .await
-                        .map_err(Error::from)
-                })
-            },
-            None => {
-                dbg!("===========================");
-                Box::pin(async {Err(Error::Connection)})
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "SET",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("SET", Some(msg.key.clone()), err))?;
+            let mut request = redis::cmd("SET");
+            request.arg(msg.key).arg(msg.value);
+            if let Some(ttl) = msg.ttl {
+                request.arg("EX").arg(ttl);
+            };
+            request.query_async(&mut *con).await.map_err(Error::from)
+        })
+    }
+}
+
+/// Actix message writing several entries in a single atomic pipeline, each with
+/// the same optional TTL.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<(), Error>")]
+pub struct MSet {
+    pub entries: Vec<(String, String)>,
+    pub ttl: Option<u32>,
+}
+
+/// Implementation of Actix Handler for MSet message.
+impl Handler<MSet> for RedisActor {
+    type Result = ResponseFuture<Result<(), Error>>;
+
+    fn handle(&mut self, msg: MSet, _: &mut Self::Context) -> Self::Result {
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or(Error::NotConnected {
+                command: "MSET",
+                key: None,
+            })?;
+            let mut con = pool.get().await.map_err(|err| pool_error("MSET", None, err))?;
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+            for (key, value) in &msg.entries {
+                let mut cmd = redis::cmd("SET");
+                cmd.arg(key).arg(value);
+                if let Some(ttl) = msg.ttl {
+                    cmd.arg("EX").arg(ttl);
+                }
+                pipe.add_command(cmd);
             }
-        }
+            pipe.query_async(&mut *con).await.map_err(Error::from)
+        })
     }
 }
 
@@ -163,7 +347,7 @@ pub enum DeleteStatus {
 }
 
 /// Struct represent deleting record message.
-#[derive(Message, Debug)]
+#[derive(Message, Debug, Clone)]
 #[rtype(result = "Result<DeleteStatus, Error>")]
 pub struct Delete {
     pub key: String,
@@ -174,11 +358,19 @@ impl Handler<Delete> for RedisActor {
     type Result = ResponseFuture<Result<DeleteStatus, Error>>;
 
     fn handle(&mut self, msg: Delete, _: &mut Self::Context) -> Self::Result {
-        let mut con = self.connection.clone().unwrap();
+        let pool = self.pool.clone();
         Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "DEL",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("DEL", Some(msg.key.clone()), err))?;
             redis::cmd("DEL")
                 .arg(msg.key)
-                .query_async(&mut con)
+                .query_async(&mut *con)
                 .await
                 .map(|res| {
                     if res > 0 {
@@ -192,6 +384,31 @@ impl Handler<Delete> for RedisActor {
     }
 }
 
+/// Lua script that releases a lock only if the caller still holds it, i.e. the
+/// value stored at `lock::{key}` still matches the token it was acquired with.
+/// This is the standard compare-and-delete check used to avoid a holder
+/// releasing a lock that has since expired and been reacquired by someone else.
+const UNLOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// Lua script that renews a lock's TTL only if the caller still holds it.
+const EXTEND_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+fn lock_key(key: &str) -> String {
+    format!("lock::{}", key)
+}
+
 /// Struct represent locking process.
 #[derive(Message, Debug, Clone)]
 #[rtype(result = "Result<LockStatus, Error>")]
@@ -203,9 +420,10 @@ pub struct Lock {
 /// Enum for representing status of Lock object in redis.
 #[derive(Debug, PartialEq)]
 pub enum LockStatus {
-    /// Lock sucsesfully created and acquired.
-    Acquired,
-    /// Lock object already acquired (locked).
+    /// Lock sucsesfully created and acquired, holding the token needed to
+    /// release or extend it.
+    Acquired(String),
+    /// Lock object already acquired (locked) by someone else.
     Locked,
 }
 
@@ -215,19 +433,28 @@ impl Handler<Lock> for RedisActor {
 
     fn handle(&mut self, msg: Lock, _: &mut Self::Context) -> Self::Result {
         debug!("Redis Lock: {}", msg.key);
-        let mut con = self.connection.clone().unwrap();
+        let pool = self.pool.clone();
         Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "LOCK",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("LOCK", Some(msg.key.clone()), err))?;
+            let token = Uuid::new_v4().to_string();
             redis::cmd("SET")
-                .arg(format!("lock::{}", msg.key))
-                .arg("")
+                .arg(lock_key(&msg.key))
+                .arg(&token)
                 .arg("NX")
                 .arg("EX")
                 .arg(msg.ttl)
-                .query_async(&mut con)
+                .query_async(&mut *con)
                 .await
                 .map(|res: Option<String>| -> LockStatus {
                     if res.is_some() {
-                        LockStatus::Acquired
+                        LockStatus::Acquired(token)
                     } else {
                         LockStatus::Locked
                     }
@@ -236,3 +463,402 @@ impl Handler<Lock> for RedisActor {
         })
     }
 }
+
+/// Struct represent releasing a held lock.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<UnlockStatus, Error>")]
+pub struct Unlock {
+    pub key: String,
+    pub token: String,
+}
+
+/// Outcome of releasing a lock.
+#[derive(Debug, PartialEq)]
+pub enum UnlockStatus {
+    /// The lock was held by this token and has been released.
+    Released,
+    /// The lock was missing or held by a different token, so nothing was deleted.
+    Mismatch,
+}
+
+/// Implementation of Actix Handler for Unlock message.
+impl Handler<Unlock> for RedisActor {
+    type Result = ResponseFuture<Result<UnlockStatus, Error>>;
+
+    fn handle(&mut self, msg: Unlock, _: &mut Self::Context) -> Self::Result {
+        debug!("Redis Unlock: {}", msg.key);
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "UNLOCK",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("UNLOCK", Some(msg.key.clone()), err))?;
+            let deleted: i32 = redis::Script::new(UNLOCK_SCRIPT)
+                .key(lock_key(&msg.key))
+                .arg(msg.token)
+                .invoke_async(&mut *con)
+                .await
+                .map_err(Error::from)?;
+            unlock_result(deleted, msg.key)
+        })
+    }
+}
+
+/// Interpret `UNLOCK_SCRIPT`'s reply: `0` means the token didn't match (or the
+/// key was already gone), `1` means it matched and was deleted, anything else
+/// is a reply the script should never produce.
+fn unlock_result(deleted: i32, key: String) -> Result<UnlockStatus, Error> {
+    match deleted {
+        0 => Ok(UnlockStatus::Mismatch),
+        1 => Ok(UnlockStatus::Released),
+        _ => Err(Error::LockPoisoned { key }),
+    }
+}
+
+/// Struct represent renewing the TTL of a held lock.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<ExtendStatus, Error>")]
+pub struct Extend {
+    pub key: String,
+    pub token: String,
+    pub ttl: u32,
+}
+
+/// Outcome of extending a lock's TTL.
+#[derive(Debug, PartialEq)]
+pub enum ExtendStatus {
+    /// The lock was held by this token and its TTL has been renewed.
+    Extended,
+    /// The lock was missing or held by a different token, so nothing was renewed.
+    Mismatch,
+}
+
+/// Implementation of Actix Handler for Extend message.
+impl Handler<Extend> for RedisActor {
+    type Result = ResponseFuture<Result<ExtendStatus, Error>>;
+
+    fn handle(&mut self, msg: Extend, _: &mut Self::Context) -> Self::Result {
+        debug!("Redis Extend: {}", msg.key);
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "EXTEND",
+                key: Some(msg.key.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("EXTEND", Some(msg.key.clone()), err))?;
+            let renewed: i32 = redis::Script::new(EXTEND_SCRIPT)
+                .key(lock_key(&msg.key))
+                .arg(msg.token)
+                .arg(u64::from(msg.ttl) * 1000)
+                .invoke_async(&mut *con)
+                .await
+                .map_err(Error::from)?;
+            extend_result(renewed, msg.key)
+        })
+    }
+}
+
+/// Interpret `EXTEND_SCRIPT`'s reply: `0` means the token didn't match (or the
+/// key was already gone), `1` means it matched and the TTL was renewed,
+/// anything else is a reply the script should never produce.
+fn extend_result(renewed: i32, key: String) -> Result<ExtendStatus, Error> {
+    match renewed {
+        0 => Ok(ExtendStatus::Mismatch),
+        1 => Ok(ExtendStatus::Extended),
+        _ => Err(Error::LockPoisoned { key }),
+    }
+}
+
+/// A single command that can be batched inside a [`Pipeline`] message.
+#[derive(Debug, Clone)]
+pub enum Command {
+    Get(Get),
+    Set(Set),
+    Delete(Delete),
+}
+
+/// Result of one [`Command`] executed as part of a [`Pipeline`], in the same
+/// position as the command that produced it.
+#[derive(Debug, PartialEq)]
+pub enum CommandResult {
+    Get(Option<String>),
+    Set(String),
+    Delete(DeleteStatus),
+}
+
+fn command_result(command: &Command, value: redis::Value) -> Result<CommandResult, Error> {
+    let (label, key) = match command {
+        Command::Get(get) => ("GET", get.key.clone()),
+        Command::Set(set) => ("SET", set.key.clone()),
+        Command::Delete(delete) => ("DEL", delete.key.clone()),
+    };
+    let serialization_error = || Error::Serialization {
+        command: label,
+        key: Some(key.clone()),
+    };
+    match command {
+        Command::Get(_) => redis::from_redis_value(&value)
+            .map(CommandResult::Get)
+            .map_err(|_| serialization_error()),
+        Command::Set(_) => redis::from_redis_value(&value)
+            .map(CommandResult::Set)
+            .map_err(|_| serialization_error()),
+        Command::Delete(_) => redis::from_redis_value::<u32>(&value)
+            .map(|res| {
+                CommandResult::Delete(if res > 0 {
+                    DeleteStatus::Deleted(res)
+                } else {
+                    DeleteStatus::Missing
+                })
+            })
+            .map_err(|_| serialization_error()),
+    }
+}
+
+/// Actix message batching unrelated commands into a single pipelined round trip.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<Vec<CommandResult>, Error>")]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+/// Implementation of Actix Handler for Pipeline message.
+impl Handler<Pipeline> for RedisActor {
+    type Result = ResponseFuture<Result<Vec<CommandResult>, Error>>;
+
+    fn handle(&mut self, msg: Pipeline, _: &mut Self::Context) -> Self::Result {
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or(Error::NotConnected {
+                command: "PIPELINE",
+                key: None,
+            })?;
+            let mut con = pool.get().await.map_err(|err| pool_error("PIPELINE", None, err))?;
+            let mut pipe = redis::pipe();
+            for command in &msg.commands {
+                match command {
+                    Command::Get(get) => {
+                        pipe.cmd("GET").arg(&get.key);
+                    }
+                    Command::Set(set) => {
+                        let mut cmd = redis::cmd("SET");
+                        cmd.arg(&set.key).arg(&set.value);
+                        if let Some(ttl) = set.ttl {
+                            cmd.arg("EX").arg(ttl);
+                        }
+                        pipe.add_command(cmd);
+                    }
+                    Command::Delete(delete) => {
+                        pipe.cmd("DEL").arg(&delete.key);
+                    }
+                }
+            }
+            let values: Vec<redis::Value> = pipe.query_async(&mut *con).await?;
+            msg.commands
+                .iter()
+                .zip(values)
+                .map(|(command, value)| command_result(command, value))
+                .collect()
+        })
+    }
+}
+
+/// Message forwarded to a [`Subscribe`] recipient for every pub/sub payload
+/// received on one of its channels.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct RedisMessage {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Actix message opening a pub/sub subscription on `channels`, forwarding every
+/// message received on them to `recipient` as a [`RedisMessage`].
+#[derive(Message)]
+#[rtype(result = "Result<(), Error>")]
+pub struct Subscribe {
+    pub channels: Vec<String>,
+    pub recipient: Recipient<RedisMessage>,
+}
+
+/// Implementation of Actix Handler for Subscribe message.
+///
+/// Pub/sub cannot share the pooled command connection, so this opens a
+/// dedicated connection *per channel* and keeps each alive for the lifetime of
+/// its own subscription, driven from its own task spawned on the actor's
+/// context. Keeping channels independent like this means `Unsubscribe` for one
+/// channel never disturbs delivery for the others, even if they were requested
+/// in the same `Subscribe` call.
+impl Handler<Subscribe> for RedisActor {
+    type Result = ResponseActFuture<Self, Result<(), Error>>;
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Self::Context) -> Self::Result {
+        let connection_info = self.connection_info.clone();
+        let tls = self.pool_config.tls.clone();
+        let channels = msg.channels;
+        let recipient = msg.recipient;
+        let fut = async move {
+            let mut subscriptions = Vec::with_capacity(channels.len());
+            for channel in channels {
+                let client = RedisConnectionManager::open_client(&connection_info, tls.clone())?;
+                let mut pubsub = client.get_async_pubsub().await?;
+                pubsub.subscribe(channel.as_str()).await?;
+                subscriptions.push((channel, pubsub));
+            }
+            Ok::<_, Error>(subscriptions)
+        };
+        Box::pin(fut.into_actor(self).map(move |res, act, ctx| {
+            let subscriptions = res?;
+            for (channel, pubsub) in subscriptions {
+                let (control_tx, mut control_rx) = mpsc::unbounded_channel();
+                act.subscriptions.insert(channel.clone(), control_tx);
+                let recipient = recipient.clone();
+                ctx.spawn(
+                    async move {
+                        let mut messages = pubsub.into_on_message();
+                        loop {
+                            tokio::select! {
+                                message = messages.next() => {
+                                    let Some(message) = message else { break };
+                                    let channel = message.get_channel_name().to_owned();
+                                    if let Ok(payload) = message.get_payload::<String>() {
+                                        recipient.do_send(RedisMessage { channel, payload });
+                                    }
+                                }
+                                _ = control_rx.recv() => break,
+                            }
+                        }
+                    }
+                    .into_actor(act),
+                );
+            }
+            Ok(())
+        }))
+    }
+}
+
+/// Actix message stopping the subscription driving `channel`, if any.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct Unsubscribe {
+    pub channel: String,
+}
+
+/// Implementation of Actix Handler for Unsubscribe message.
+impl Handler<Unsubscribe> for RedisActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Unsubscribe, _: &mut Self::Context) -> Self::Result {
+        if let Some(control_tx) = self.subscriptions.remove(&msg.channel) {
+            let _ = control_tx.send(SubscriptionControl::Unsubscribe);
+        }
+    }
+}
+
+/// Actix message publishing `payload` to `channel`, returning the number of
+/// subscribers that received it.
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "Result<i32, Error>")]
+pub struct Publish {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Implementation of Actix Handler for Publish message.
+impl Handler<Publish> for RedisActor {
+    type Result = ResponseFuture<Result<i32, Error>>;
+
+    fn handle(&mut self, msg: Publish, _: &mut Self::Context) -> Self::Result {
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let pool = pool.ok_or_else(|| Error::NotConnected {
+                command: "PUBLISH",
+                key: Some(msg.channel.clone()),
+            })?;
+            let mut con = pool
+                .get()
+                .await
+                .map_err(|err| pool_error("PUBLISH", Some(msg.channel.clone()), err))?;
+            redis::cmd("PUBLISH")
+                .arg(msg.channel)
+                .arg(msg.payload)
+                .query_async(&mut *con)
+                .await
+                .map_err(Error::from)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_result_decodes_get() {
+        let command = Command::Get(Get { key: "k".to_owned() });
+        let result = command_result(&command, redis::Value::BulkString(b"v".to_vec())).unwrap();
+        assert_eq!(result, CommandResult::Get(Some("v".to_owned())));
+    }
+
+    #[test]
+    fn command_result_decodes_get_miss() {
+        let command = Command::Get(Get { key: "k".to_owned() });
+        let result = command_result(&command, redis::Value::Nil).unwrap();
+        assert_eq!(result, CommandResult::Get(None));
+    }
+
+    #[test]
+    fn command_result_decodes_set() {
+        let command = Command::Set(Set {
+            key: "k".to_owned(),
+            value: "v".to_owned(),
+            ttl: None,
+        });
+        let result = command_result(&command, redis::Value::Okay).unwrap();
+        assert_eq!(result, CommandResult::Set("OK".to_owned()));
+    }
+
+    #[test]
+    fn command_result_decodes_delete() {
+        let command = Command::Delete(Delete { key: "k".to_owned() });
+        let result = command_result(&command, redis::Value::Int(1)).unwrap();
+        assert_eq!(result, CommandResult::Delete(DeleteStatus::Deleted(1)));
+
+        let result = command_result(&command, redis::Value::Int(0)).unwrap();
+        assert_eq!(result, CommandResult::Delete(DeleteStatus::Missing));
+    }
+
+    #[test]
+    fn command_result_reports_serialization_errors() {
+        let command = Command::Get(Get { key: "k".to_owned() });
+        let err = command_result(&command, redis::Value::Array(vec![])).unwrap_err();
+        assert!(matches!(err, Error::Serialization { command: "GET", .. }));
+    }
+
+    #[test]
+    fn unlock_result_dispatches_on_script_reply() {
+        assert_eq!(unlock_result(0, "k".to_owned()).unwrap(), UnlockStatus::Mismatch);
+        assert_eq!(unlock_result(1, "k".to_owned()).unwrap(), UnlockStatus::Released);
+        assert!(matches!(
+            unlock_result(2, "k".to_owned()).unwrap_err(),
+            Error::LockPoisoned { key } if key == "k"
+        ));
+    }
+
+    #[test]
+    fn extend_result_dispatches_on_script_reply() {
+        assert_eq!(extend_result(0, "k".to_owned()).unwrap(), ExtendStatus::Mismatch);
+        assert_eq!(extend_result(1, "k".to_owned()).unwrap(), ExtendStatus::Extended);
+        assert!(matches!(
+            extend_result(2, "k".to_owned()).unwrap_err(),
+            Error::LockPoisoned { key } if key == "k"
+        ));
+    }
+}
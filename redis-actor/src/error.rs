@@ -0,0 +1,76 @@
+use std::fmt;
+
+/// Errors produced by [`RedisActor`](crate::actor::RedisActor) and its message handlers.
+///
+/// Variants carry the command and key involved so callers can log or match on
+/// them. `PoolTimeout` is always transient (no connection became free in
+/// time); `Io` wraps the `redis::RedisError` as is and covers both transient
+/// failures, such as a dropped connection, and fatal ones, such as failed
+/// authentication.
+#[derive(Debug)]
+pub enum Error {
+    /// No pool has been established yet (the actor hasn't finished connecting,
+    /// or failed to).
+    NotConnected {
+        command: &'static str,
+        key: Option<String>,
+    },
+    /// Checking out a connection from the pool timed out.
+    PoolTimeout {
+        command: &'static str,
+        key: Option<String>,
+    },
+    /// A connection to Redis could not be opened, or the command reached Redis
+    /// but failed, or the connection dropped mid-command.
+    Io(redis::RedisError),
+    /// Redis replied, but the reply couldn't be decoded into the expected type.
+    Serialization {
+        command: &'static str,
+        key: Option<String>,
+    },
+    /// A lock's compare-and-swap script saw an unexpected reply, neither a
+    /// match nor a clean mismatch.
+    LockPoisoned { key: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotConnected { command, key } => {
+                write!(f, "no redis connection available for {}", describe(command, key))
+            }
+            Error::PoolTimeout { command, key } => {
+                write!(f, "timed out checking out a connection for {}", describe(command, key))
+            }
+            Error::Io(err) => write!(f, "redis command failed: {}", err),
+            Error::Serialization { command, key } => {
+                write!(f, "unexpected reply decoding {}", describe(command, key))
+            }
+            Error::LockPoisoned { key } => {
+                write!(f, "lock script for key \"{}\" returned an unexpected reply", key)
+            }
+        }
+    }
+}
+
+fn describe(command: &str, key: &Option<String>) -> String {
+    match key {
+        Some(key) => format!("{} \"{}\"", command, key),
+        None => command.to_owned(),
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<redis::RedisError> for Error {
+    fn from(err: redis::RedisError) -> Self {
+        Error::Io(err)
+    }
+}
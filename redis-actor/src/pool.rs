@@ -0,0 +1,128 @@
+use crate::error::Error;
+use async_trait::async_trait;
+use bb8::ManageConnection;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{Client, RedisError};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Exponential-backoff parameters applied to each pooled connection's automatic
+/// reconnect attempts.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub factor: u64,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        ReconnectBackoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            factor: 2,
+        }
+    }
+}
+
+/// TLS material for a `rediss://` endpoint: a custom CA to trust and/or a
+/// client certificate/key pair for mutual TLS. Only consulted when the crate
+/// is built with the `tls-rustls` or `tls-native-tls` feature; otherwise a
+/// `rediss://` URL is rejected by `redis::Client` itself.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    pub ca_cert_path: Option<PathBuf>,
+    pub client_cert_path: Option<PathBuf>,
+    pub client_key_path: Option<PathBuf>,
+}
+
+/// `bb8::ManageConnection` backed by `redis::aio::ConnectionManager`.
+///
+/// Unlike a plain multiplexed connection, a `ConnectionManager` transparently
+/// re-establishes the socket after a transient disconnect, so a pooled
+/// connection heals itself instead of being torn down and recreated by the
+/// pool. The command that hit the disconnect is not retried: it fails with the
+/// underlying `RedisError`.
+#[derive(Clone)]
+pub struct RedisConnectionManager {
+    client: Client,
+    backoff: ReconnectBackoff,
+}
+
+impl RedisConnectionManager {
+    pub fn new(
+        connection_info: &str,
+        backoff: ReconnectBackoff,
+        tls: TlsConfig,
+    ) -> Result<Self, Error> {
+        let client = Self::open_client(connection_info, tls)?;
+        Ok(RedisConnectionManager { client, backoff })
+    }
+
+    /// Build a `Client` for `connection_info`, honoring `tls` when the crate is
+    /// built with a TLS feature. Shared by the pool and by call sites (like
+    /// pub/sub) that need their own dedicated connection outside the pool.
+    #[cfg(any(feature = "tls-rustls", feature = "tls-native-tls"))]
+    pub(crate) fn open_client(connection_info: &str, tls: TlsConfig) -> Result<Client, Error> {
+        if tls.ca_cert_path.is_none() && tls.client_cert_path.is_none() {
+            return Client::open(connection_info).map_err(Error::from);
+        }
+        let root_cert = tls.ca_cert_path.map(std::fs::read).transpose().map_err(|_| {
+            Error::NotConnected {
+                command: "TLS_CA_CERT",
+                key: None,
+            }
+        })?;
+        let client_tls = match (tls.client_cert_path, tls.client_key_path) {
+            (Some(cert_path), Some(key_path)) => Some(redis::ClientTlsConfig {
+                client_cert: std::fs::read(cert_path).map_err(|_| Error::NotConnected {
+                    command: "TLS_CLIENT_CERT",
+                    key: None,
+                })?,
+                client_key: std::fs::read(key_path).map_err(|_| Error::NotConnected {
+                    command: "TLS_CLIENT_KEY",
+                    key: None,
+                })?,
+            }),
+            _ => None,
+        };
+        Client::build_with_tls(connection_info, redis::TlsCertificates { client_tls, root_cert })
+            .map_err(Error::from)
+    }
+
+    #[cfg(not(any(feature = "tls-rustls", feature = "tls-native-tls")))]
+    pub(crate) fn open_client(connection_info: &str, _tls: TlsConfig) -> Result<Client, Error> {
+        Client::open(connection_info).map_err(Error::from)
+    }
+}
+
+#[async_trait]
+impl ManageConnection for RedisConnectionManager {
+    type Connection = ConnectionManager;
+    type Error = RedisError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        // `ConnectionManagerConfig`'s schedule is `factor * base^n` milliseconds,
+        // not "start at `initial_delay`, grow by `factor`". `base` is our growth
+        // factor, and `factor` (the ms multiplier) is derived so the first retry
+        // lands on `initial_delay`: `factor_ms * base^1 == initial_delay`.
+        let base = self.backoff.factor.max(1);
+        let initial_delay_ms = self.backoff.initial_delay.as_millis() as u64;
+        let factor_ms = (initial_delay_ms / base).max(1);
+        let config = ConnectionManagerConfig::new()
+            .set_exponent_base(base)
+            .set_factor(factor_ms)
+            .set_max_delay(self.backoff.max_delay.as_millis() as u64);
+        ConnectionManager::new_with_config(self.client.clone(), config).await
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        redis::cmd("PING").query_async(conn).await
+    }
+
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        // `ConnectionManager` reconnects on its own, so the pool should never
+        // discard one outright; let `is_valid` decide instead.
+        false
+    }
+}